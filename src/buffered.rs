@@ -0,0 +1,127 @@
+use crate::{ReceiveError, UpdateError};
+use std::sync::{Arc, Mutex, Weak};
+
+/// Internal ring buffer state shared between a [`BufferedReceiver`] and its [`BufferedUpdater`].
+pub(crate) struct RingShared<T> {
+    pub(crate) capacity: u64,
+    pub(crate) ring: Mutex<RingState<T>>,
+}
+
+pub(crate) struct RingState<T> {
+    pub(crate) slots: Vec<Option<T>>,
+    /// Sequence number of the next slot an [`update`](BufferedUpdater::update) will write.
+    pub(crate) next_seq: u64,
+}
+
+/// The receiver half of a bounded-history update channel created with
+/// [`channel_buffered`](crate::channel_buffered).
+///
+/// Unlike [`Receiver`](crate::Receiver), every update written by the [`BufferedUpdater`] is
+/// retained until it falls out of the ring buffer, so a slow receiver observes each
+/// intermediate value instead of only the latest one.
+pub struct BufferedReceiver<T> {
+    shared: Arc<RingShared<T>>,
+    cursor: u64,
+}
+
+impl<T> BufferedReceiver<T> {
+    /// Checks if at least one updater exists
+    pub fn has_updater(&self) -> bool {
+        Arc::weak_count(&self.shared) != 0
+    }
+}
+
+impl<T> BufferedReceiver<T>
+where
+    T: Clone,
+{
+    /// Returns the next value this receiver hasn't seen yet. Returns `Ok(None)` if the
+    /// updater hasn't written anything new. If this receiver fell more than the channel's
+    /// capacity behind, returns [`ReceiveError::Lagged`] with the number of skipped values
+    /// and fast-forwards the read cursor to the oldest value still retained.
+    ///
+    /// Note this returns the value just read, unlike [`Receiver::recv_update`](crate::Receiver::recv_update)
+    /// which returns the *previous* buffer contents — the two types don't share a storage model,
+    /// so they're named differently to avoid suggesting the same semantics.
+    pub fn recv(&mut self) -> Result<Option<T>, ReceiveError> {
+        let ring = self.shared.ring.lock().map_err(|_| ReceiveError::Closed)?;
+
+        let oldest = ring.next_seq.saturating_sub(self.shared.capacity);
+        if self.cursor < oldest {
+            let skipped = oldest - self.cursor;
+            self.cursor = oldest;
+            return Err(ReceiveError::Lagged(skipped));
+        }
+
+        if self.cursor >= ring.next_seq {
+            return Ok(None);
+        }
+
+        let idx = (self.cursor % self.shared.capacity) as usize;
+        let value = ring.slots[idx].clone();
+        self.cursor += 1;
+        Ok(value)
+    }
+}
+
+impl<T> Clone for BufferedReceiver<T> {
+    fn clone(&self) -> Self {
+        Self {
+            shared: Arc::clone(&self.shared),
+            cursor: self.cursor,
+        }
+    }
+}
+
+unsafe impl<T> Send for BufferedReceiver<T> where T: Clone {}
+
+/// The updater half of a bounded-history update channel created with
+/// [`channel_buffered`](crate::channel_buffered).
+pub struct BufferedUpdater<T> {
+    pub(crate) lock: Weak<RingShared<T>>,
+}
+
+impl<T> BufferedUpdater<T> {
+    /// Checks if at least one receiver exists
+    pub fn has_receiver(&self) -> bool {
+        self.lock.upgrade().is_some()
+    }
+
+    /// Pushes a new value into the ring buffer. A receiver that hasn't caught up within the
+    /// channel's capacity will observe a [`ReceiveError::Lagged`] on its next receive instead
+    /// of silently missing the older value this update overwrites.
+    pub fn update(&self, value: T) -> Result<(), UpdateError<T>> {
+        if let Some(shared) = self.lock.upgrade() {
+            if let Ok(mut ring) = shared.ring.lock() {
+                let idx = (ring.next_seq % shared.capacity) as usize;
+                ring.slots[idx] = Some(value);
+                ring.next_seq += 1;
+                Ok(())
+            } else {
+                Err(UpdateError::Poisoned(value))
+            }
+        } else {
+            Err(UpdateError::NoReceiver(value))
+        }
+    }
+}
+
+unsafe impl<T> Send for BufferedUpdater<T> {}
+
+pub(crate) fn new<T>(capacity: u64) -> (BufferedReceiver<T>, BufferedUpdater<T>) {
+    assert!(capacity > 0, "channel_buffered capacity must be greater than zero");
+
+    let shared = Arc::new(RingShared {
+        capacity,
+        ring: Mutex::new(RingState {
+            slots: (0..capacity).map(|_| None).collect(),
+            next_seq: 0,
+        }),
+    });
+    let weak = Arc::downgrade(&shared);
+
+    let rec = BufferedReceiver { shared, cursor: 0 };
+    let upd = BufferedUpdater { lock: weak };
+
+    (rec, upd)
+}
@@ -35,23 +35,25 @@
 //! ```
 //!
 
+mod buffered;
 mod receiver;
+mod shared;
 mod updater;
 
+pub use buffered::{BufferedReceiver, BufferedUpdater};
 pub use receiver::*;
 pub use updater::*;
 
-use std::{
-    cell::UnsafeCell,
-    sync::{Arc, RwLock},
-};
+use shared::Shared;
+use std::{cell::Cell, cell::UnsafeCell, sync::Arc};
 
 /// Create a channel where the receiver starts with value as internal value
 pub fn channel_with<T>(value: T) -> (Receiver<T>, Updater<T>) {
-    let shared = Arc::new(RwLock::new(None));
+    let shared = Arc::new(Shared::new(None, false));
     let weak = Arc::downgrade(&shared);
     let rec = Receiver {
         cell: UnsafeCell::new(value),
+        seen: Cell::new(0),
         shared,
     };
 
@@ -62,10 +64,11 @@ pub fn channel_with<T>(value: T) -> (Receiver<T>, Updater<T>) {
 
 /// Creates a channel with None as start value
 pub fn channel<T>() -> (Receiver<Option<T>>, Updater<Option<T>>) {
-    let shared = Arc::new(RwLock::new(None));
+    let shared = Arc::new(Shared::new(None, false));
     let weak = Arc::downgrade(&shared);
     let rec = Receiver {
         cell: UnsafeCell::new(None),
+        seen: Cell::new(0),
         shared,
     };
 
@@ -79,10 +82,11 @@ pub fn channel_default<T>() -> (Receiver<T>, Updater<T>)
 where
     T: Default,
 {
-    let shared = Arc::new(RwLock::new(None));
+    let shared = Arc::new(Shared::new(None, false));
     let weak = Arc::downgrade(&shared);
     let rec = Receiver {
         cell: UnsafeCell::new(Default::default()),
+        seen: Cell::new(0),
         shared,
     };
 
@@ -91,6 +95,40 @@ where
     (rec, upd)
 }
 
+/// Starts the channel with the default value of T, and treats a poisoned lock as recoverable
+/// instead of permanently bricking the channel. Since the stored value is always either fully
+/// replaced or untouched, a writer panicking mid-`update` can never leave it inconsistent, so
+/// operations on this channel read/take the inner value instead of surfacing an error.
+pub fn channel_ignore_poison<T>() -> (Receiver<T>, Updater<T>)
+where
+    T: Default,
+{
+    let shared = Arc::new(Shared::new(None, true));
+    let weak = Arc::downgrade(&shared);
+    let rec = Receiver {
+        cell: UnsafeCell::new(Default::default()),
+        seen: Cell::new(0),
+        shared,
+    };
+
+    let upd = Updater { lock: weak };
+
+    (rec, upd)
+}
+
+/// Creates a bounded-history channel that retains the last `capacity` updates in a ring
+/// buffer instead of only the latest one, so a receiver that polls infrequently sees every
+/// intermediate value (or a [`ReceiveError::Lagged`] count of how many it missed) rather than
+/// silently skipping them. Prefer [`channel`]/[`channel_with`]/[`channel_default`] unless you
+/// specifically need that history.
+///
+/// # Panics
+///
+/// Panics if `capacity` is zero.
+pub fn channel_buffered<T>(capacity: u64) -> (BufferedReceiver<T>, BufferedUpdater<T>) {
+    buffered::new(capacity)
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -99,6 +137,38 @@ mod test {
         thread::spawn,
     };
 
+    #[test]
+    fn changed_wakes_on_last_updater_drop() {
+        use std::{
+            future::Future,
+            pin::Pin,
+            sync::atomic::{AtomicBool, Ordering},
+            task::{Context, Poll, Wake, Waker},
+        };
+
+        struct FlagWake(AtomicBool);
+        impl Wake for FlagWake {
+            fn wake(self: Arc<Self>) {
+                self.0.store(true, Ordering::Release);
+            }
+        }
+
+        let (mut rec, upd) = channel_default::<i32>();
+        let flag = Arc::new(FlagWake(AtomicBool::new(false)));
+        let waker = Waker::from(Arc::clone(&flag));
+        let mut cx = Context::from_waker(&waker);
+
+        let mut fut = rec.changed();
+        assert!(matches!(Pin::new(&mut fut).poll(&mut cx), Poll::Pending));
+
+        drop(upd);
+
+        assert!(
+            flag.0.load(Ordering::Acquire),
+            "dropping the last updater should wake a pending changed() future"
+        );
+    }
+
     #[test]
     fn creation_default() {
         let (rec, upd) = channel_default::<i32>();
@@ -107,6 +177,22 @@ mod test {
         assert_eq!(rec.borrow().clone(), 0);
     }
 
+    #[test]
+    fn has_changed_cleared_by_recv_and_take_update() {
+        let (mut rec, upd) = channel_default::<i32>();
+        assert!(!rec.has_changed().unwrap());
+
+        upd.update(1).unwrap();
+        assert!(rec.has_changed().unwrap());
+        rec.recv_update().unwrap();
+        assert!(!rec.has_changed().unwrap(), "recv_update should mark the version as seen");
+
+        upd.update(2).unwrap();
+        assert!(rec.has_changed().unwrap());
+        rec.take_update().unwrap();
+        assert!(!rec.has_changed().unwrap(), "take_update should mark the version as seen");
+    }
+
     #[test]
     fn take() {
         let (mut rec, upd) = channel_default::<i32>();
@@ -153,6 +239,51 @@ mod test {
         assert!(!upd.has_receiver());
     }
 
+    #[test]
+    fn wait_closed_unblocks_after_last_receiver_drop() {
+        let (rec, upd) = channel_default::<i32>();
+
+        let th = spawn(move || {
+            drop(rec);
+        });
+
+        upd.wait_closed();
+        th.join().unwrap();
+        assert!(!upd.has_receiver());
+    }
+
+    #[test]
+    fn closed_future_wakes_on_last_receiver_drop() {
+        use std::{
+            future::Future,
+            pin::Pin,
+            sync::atomic::{AtomicBool, Ordering},
+            task::{Context, Poll, Wake, Waker},
+        };
+
+        struct FlagWake(AtomicBool);
+        impl Wake for FlagWake {
+            fn wake(self: Arc<Self>) {
+                self.0.store(true, Ordering::Release);
+            }
+        }
+
+        let (rec, upd) = channel_default::<i32>();
+        let flag = Arc::new(FlagWake(AtomicBool::new(false)));
+        let waker = Waker::from(Arc::clone(&flag));
+        let mut cx = Context::from_waker(&waker);
+
+        let mut fut = upd.closed();
+        assert!(matches!(Pin::new(&mut fut).poll(&mut cx), Poll::Pending));
+
+        drop(rec);
+
+        assert!(
+            flag.0.load(Ordering::Acquire),
+            "dropping the last receiver should wake a pending closed() future"
+        );
+    }
+
     fn barrier_pair(n: usize) -> (Arc<Barrier>, Arc<Barrier>) {
         let barrier = Barrier::new(n);
         let b = Arc::new(barrier);
@@ -254,4 +385,90 @@ mod test {
         // Shared value is 12 and internal value 12
         assert_eq!(*receiver.borrow(), 12);
     }
+
+    #[test]
+    fn ignore_poison_reports_and_clears_recovery() {
+        use std::panic;
+
+        // Panics when dropped while armed, so replacing it mid-`update` poisons the lock.
+        #[derive(Debug, Default)]
+        struct BoomOnDrop(i32, bool);
+        impl Drop for BoomOnDrop {
+            fn drop(&mut self) {
+                if self.1 {
+                    panic!("boom");
+                }
+            }
+        }
+        impl Clone for BoomOnDrop {
+            fn clone(&self) -> Self {
+                BoomOnDrop(self.0, false)
+            }
+        }
+
+        let (mut rec, upd) = channel_ignore_poison::<BoomOnDrop>();
+        assert!(!rec.poison_recovered());
+        assert!(!upd.poison_recovered());
+
+        upd.update(BoomOnDrop(1, true)).unwrap();
+        let upd2 = upd.clone();
+        let _ = panic::catch_unwind(panic::AssertUnwindSafe(move || {
+            upd2.update(BoomOnDrop(2, false)).unwrap();
+        }));
+
+        // The next access recovers from the poisoned lock instead of erroring, and both
+        // halves can observe that the recovery happened.
+        rec.recv_update().unwrap();
+        assert!(rec.poison_recovered());
+        assert!(upd.poison_recovered());
+
+        rec.clear_poison();
+        assert!(!rec.poison_recovered());
+        assert!(!upd.poison_recovered());
+    }
+
+    #[test]
+    fn try_methods_would_block_while_locked() {
+        let (rec, upd) = channel_default::<i32>();
+        upd.update(1).unwrap();
+
+        let guard = rec.borrow_locked().unwrap();
+        assert!(matches!(upd.try_update(2), Err(UpdateError::WouldBlock(2))));
+        assert_eq!(
+            unsafe { rec.try_take_update_unsafe() },
+            Err(ReceiveError::WouldBlock)
+        );
+        drop(guard);
+
+        upd.try_update(2).unwrap();
+        // Returns the previous buffer contents (the default `0`, since the earlier take
+        // attempt never got past WouldBlock), mirroring `take_update`'s semantics.
+        assert_eq!(unsafe { rec.try_take_update_unsafe() }.unwrap(), Some(0));
+    }
+
+    #[test]
+    fn buffered_in_order() {
+        let (mut rec, upd) = channel_buffered::<i32>(3);
+        upd.update(1).unwrap();
+        upd.update(2).unwrap();
+
+        assert_eq!(rec.recv().unwrap(), Some(1));
+        assert_eq!(rec.recv().unwrap(), Some(2));
+        assert_eq!(rec.recv().unwrap(), None);
+    }
+
+    #[test]
+    fn buffered_lagged_fast_forwards_cursor() {
+        let (mut rec, upd) = channel_buffered::<i32>(2);
+
+        for i in 0..5 {
+            upd.update(i).unwrap();
+        }
+
+        // Capacity 2, 5 updates written: only 3 and 4 remain, the rest were skipped.
+        assert_eq!(rec.recv(), Err(ReceiveError::Lagged(3)));
+        assert_eq!(rec.recv().unwrap(), Some(3));
+        assert_eq!(rec.recv().unwrap(), Some(4));
+        assert_eq!(rec.recv().unwrap(), None);
+    }
 }
@@ -1,12 +1,17 @@
+use crate::shared::Shared;
 use std::{
-    cell::UnsafeCell,
-    sync::{Arc, RwLock, RwLockReadGuard},
+    cell::{Cell, UnsafeCell},
+    future::Future,
+    pin::Pin,
+    sync::{atomic::Ordering, Arc, RwLockReadGuard, TryLockError},
+    task::{Context, Poll},
 };
 
 #[derive(Debug)]
 pub struct Receiver<T> {
     pub(crate) cell: UnsafeCell<T>,
-    pub(crate) shared: Arc<RwLock<Option<T>>>,
+    pub(crate) shared: Arc<Shared<T>>,
+    pub(crate) seen: Cell<u64>,
 }
 
 impl<T> Receiver<T> {
@@ -23,7 +28,42 @@ impl<T> Receiver<T> {
     /// This might open new possiblities, but it might result in undefined behavior if there are immutable borrows
     /// created with [`Receiver::borrow`](struct.Receiver.html#method.borrow)
     pub unsafe fn take_update_unsafe(&self) -> Result<Option<T>, ReceiveError> {
-        let mut lock = self.shared.write().map_err(|_| ReceiveError)?;
+        let mut lock = self.shared.write_value().map_err(|_| ReceiveError::Closed)?;
+        self.seen.set(self.shared.version.load(Ordering::Acquire));
+
+        if let Some(s) = lock.take() {
+            let old = std::mem::replace(&mut *self.cell.get(), s);
+            Ok(Some(old))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Same as [`Receiver::take_update`](struct.Receiver.html#method.take_update) but never
+    /// blocks: returns [`ReceiveError::WouldBlock`] instead of waiting for the lock, for
+    /// latency-sensitive callers that would rather skip an update this tick.
+    pub fn try_take_update(&mut self) -> Result<Option<T>, ReceiveError> {
+        unsafe { self.try_take_update_unsafe() }
+    }
+
+    /// Same as [`Receiver::try_take_update`](struct.Receiver.html#method.try_take_update) but
+    /// without borrowing mutably. This might open new possiblities, but it might result in
+    /// undefined behavior if there are immutable borrows created with
+    /// [`Receiver::borrow`](struct.Receiver.html#method.borrow)
+    ///
+    /// # Safety
+    ///
+    /// The caller must ensure there are no live borrows of the value returned by
+    /// [`Receiver::borrow`](struct.Receiver.html#method.borrow) or
+    /// [`Receiver::borrow_mut`](struct.Receiver.html#method.borrow_mut) for the duration of this call,
+    /// since it writes to the same internal buffer through a shared reference.
+    pub unsafe fn try_take_update_unsafe(&self) -> Result<Option<T>, ReceiveError> {
+        let mut lock = match self.shared.try_write_value() {
+            Ok(lock) => lock,
+            Err(TryLockError::WouldBlock) => return Err(ReceiveError::WouldBlock),
+            Err(TryLockError::Poisoned(_)) => return Err(ReceiveError::Closed),
+        };
+        self.seen.set(self.shared.version.load(Ordering::Acquire));
 
         if let Some(s) = lock.take() {
             let old = std::mem::replace(&mut *self.cell.get(), s);
@@ -45,19 +85,63 @@ impl<T> Receiver<T> {
 
     /// Unwrap the value contained in the buffer of this receiver
     pub fn into_inner(self) -> T {
-        self.cell.into_inner()
+        let this = std::mem::ManuallyDrop::new(self);
+
+        if this.shared.receiver_count.fetch_sub(1, Ordering::AcqRel) == 1 {
+            this.shared.wake_closed();
+        }
+
+        // SAFETY: `this` is wrapped in `ManuallyDrop` so `Receiver`'s `Drop` impl (whose
+        // bookkeeping we just ran above) never runs, and each field below is read exactly once.
+        unsafe {
+            let shared = std::ptr::read(&this.shared);
+            let value = std::ptr::read(this.cell.get());
+            drop(shared);
+            value
+        }
     }
 
     /// Get the latest value (not the value in the buffer) and return it while holding a read lock to it.
     /// It is not recommended to hold on to this lock for long.
     pub fn borrow_locked<'a>(&'a self) -> Result<RwLockReadGuard<'a, Option<T>>, ReceiveError> {
-        self.shared.read().map_err(|_| ReceiveError)
+        self.shared.read_value().map_err(|_| ReceiveError::Closed)
     }
 
     /// Checks if at least one updater exists
     pub fn has_updater(&self) -> bool {
         Arc::weak_count(&self.shared) != 0
     }
+
+    /// Checks whether the updater has written a value since this receiver last observed one,
+    /// without touching `T` at all. Unlike [`recv_update_checked`](struct.Receiver.html#method.recv_update_checked)
+    /// this works for any `T`, since it only compares version counters instead of the values themselves.
+    /// The version is marked as seen by the methods that copy or take the shared value into the
+    /// buffer (`recv_update`, `take_update`, `borrow_and_update` and their variants) and by a
+    /// completed [`changed`](struct.Receiver.html#method.changed), so this won't report a stale
+    /// change as still pending once one of those has consumed it. `get_cloned` and
+    /// `borrow_locked` read the shared value without marking it as seen.
+    pub fn has_changed(&self) -> Result<bool, ReceiveError> {
+        Ok(self.shared.version.load(Ordering::Acquire) != self.seen.get())
+    }
+
+    /// Clears the poisoned state of the channel's lock, if a panicking writer poisoned it,
+    /// so that operations on a regular (non-[`channel_ignore_poison`](crate::channel_ignore_poison))
+    /// channel stop returning [`ReceiveError::Closed`] for it. Also resets
+    /// [`poison_recovered`](Receiver::poison_recovered) back to `false`.
+    pub fn clear_poison(&self) {
+        self.shared.value.clear_poison();
+        self.shared.poison_recovered.store(false, Ordering::Release);
+    }
+
+    /// Returns whether an operation on this channel has recovered from a poisoned lock by using
+    /// the inner value anyway, rather than surfacing it as an error. Always `false` on a
+    /// regular channel, since there [`ReceiveError::Closed`] is returned instead of recovering.
+    /// Meaningful on a [`channel_ignore_poison`](crate::channel_ignore_poison) channel, where
+    /// recovery happens automatically and silently unless a caller checks this to log it.
+    /// Cleared by [`clear_poison`](Receiver::clear_poison).
+    pub fn poison_recovered(&self) -> bool {
+        self.shared.poison_recovered.load(Ordering::Acquire)
+    }
 }
 
 impl<T> Receiver<T>
@@ -72,11 +156,64 @@ where
         }
     }
 
+    /// Clones the latest value into the internal buffer, if one has been written, and marks the
+    /// current version as seen so that [`has_changed`](struct.Receiver.html#method.has_changed)
+    /// and [`changed`](struct.Receiver.html#method.changed) won't wake again for it. Returns a
+    /// borrow of the buffer, mirroring [`borrow`](struct.Receiver.html#method.borrow).
+    pub fn borrow_and_update(&mut self) -> &T {
+        if let Ok(lock) = self.shared.read_value() {
+            let version = self.shared.version.load(Ordering::Acquire);
+            if let Some(s) = &*lock {
+                unsafe {
+                    *self.cell.get() = s.clone();
+                }
+            }
+            self.seen.set(version);
+        }
+
+        self.borrow()
+    }
+
     /// Same as [`Receiver::recv_update`](struct.Receiver.html#method.recv_update) but without borrowing mutably.
     /// This might open new possiblities, but it might result in undefined behavior if there are immutable borrows
     /// created with [`Receiver::borrow`](struct.Receiver.html#method.borrow)
     pub unsafe fn recv_update_unsafe(&self) -> Result<Option<T>, ReceiveError> {
-        let lock = self.shared.read().map_err(|_| ReceiveError)?;
+        let lock = self.shared.read_value().map_err(|_| ReceiveError::Closed)?;
+        self.seen.set(self.shared.version.load(Ordering::Acquire));
+        if let Some(s) = &*lock {
+            let old = std::mem::replace(&mut *self.cell.get(), s.clone());
+            Ok(Some(old))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Same as [`Receiver::recv_update`](struct.Receiver.html#method.recv_update) but never
+    /// blocks: returns [`ReceiveError::WouldBlock`] instead of waiting for the lock, for
+    /// latency-sensitive callers that would rather skip an update this tick.
+    pub fn try_recv_update(&mut self) -> Result<Option<T>, ReceiveError> {
+        unsafe { self.try_recv_update_unsafe() }
+    }
+
+    /// Same as [`Receiver::try_recv_update`](struct.Receiver.html#method.try_recv_update) but
+    /// without borrowing mutably. This might open new possiblities, but it might result in
+    /// undefined behavior if there are immutable borrows created with
+    /// [`Receiver::borrow`](struct.Receiver.html#method.borrow)
+    ///
+    /// # Safety
+    ///
+    /// The caller must ensure there are no live borrows of the value returned by
+    /// [`Receiver::borrow`](struct.Receiver.html#method.borrow) or
+    /// [`Receiver::borrow_mut`](struct.Receiver.html#method.borrow_mut) for the duration of this call,
+    /// since it writes to the same internal buffer through a shared reference.
+    pub unsafe fn try_recv_update_unsafe(&self) -> Result<Option<T>, ReceiveError> {
+        let lock = match self.shared.try_read_value() {
+            Ok(lock) => lock,
+            Err(TryLockError::WouldBlock) => return Err(ReceiveError::WouldBlock),
+            Err(TryLockError::Poisoned(_)) => return Err(ReceiveError::Closed),
+        };
+        self.seen.set(self.shared.version.load(Ordering::Acquire));
+
         if let Some(s) = &*lock {
             let old = std::mem::replace(&mut *self.cell.get(), s.clone());
             Ok(Some(old))
@@ -88,8 +225,8 @@ where
     /// Get the latest value (not the value in the buffer) cloned
     pub fn get_cloned(&self) -> Result<Option<T>, ReceiveError> {
         self.shared
-            .read()
-            .map_err(|_| ReceiveError)
+            .read_value()
+            .map_err(|_| ReceiveError::Closed)
             .map(|v| v.clone())
     }
 }
@@ -107,7 +244,8 @@ where
     /// This might open new possiblities, but it might result in undefined behavior if there are immutable borrows
     /// created with [`Receiver::borrow`](struct.Receiver.html#method.borrow)
     pub unsafe fn recv_update_checked_unsafe(&self) -> Result<Option<T>, ReceiveError> {
-        let lock = self.shared.read().map_err(|_| ReceiveError)?;
+        let lock = self.shared.read_value().map_err(|_| ReceiveError::Closed)?;
+        self.seen.set(self.shared.version.load(Ordering::Acquire));
 
         if let Some(value) = &*lock {
             let vptr = self.cell.get();
@@ -131,7 +269,8 @@ where
     /// This might open new possiblities, but it might result in undefined behavior if there are immutable borrows
     /// created with [`Receiver::borrow`](struct.Receiver.html#method.borrow)
     pub unsafe fn take_update_checked_unsafe(&self) -> Result<Option<T>, ReceiveError> {
-        let mut lock = self.shared.write().map_err(|_| ReceiveError)?;
+        let mut lock = self.shared.write_value().map_err(|_| ReceiveError::Closed)?;
+        self.seen.set(self.shared.version.load(Ordering::Acquire));
 
         if let Some(value) = lock.take() {
             let vptr = self.cell.get();
@@ -152,15 +291,83 @@ where
     T: Clone,
 {
     fn clone(&self) -> Self {
+        self.shared.receiver_count.fetch_add(1, Ordering::AcqRel);
+
         Self {
             shared: Arc::clone(&self.shared),
             cell: UnsafeCell::new(unsafe { (&*self.cell.get()).clone() }),
+            seen: self.seen.clone(),
         }
     }
 }
 
 unsafe impl<T> Send for Receiver<T> where T: Clone {}
 
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        if self.shared.receiver_count.fetch_sub(1, Ordering::AcqRel) == 1 {
+            self.shared.wake_closed();
+        }
+    }
+}
+
+impl<T> Receiver<T> {
+    /// Waits until the updater writes a value newer than the one this receiver last observed.
+    /// Resolves with [`ReceiveError`] as soon as every [`Updater`](crate::Updater) has been
+    /// dropped, so awaiting this future never hangs forever on an abandoned channel.
+    pub fn changed(&mut self) -> Changed<'_, T> {
+        Changed { receiver: self }
+    }
+}
+
+/// Future returned by [`Receiver::changed`].
+pub struct Changed<'a, T> {
+    receiver: &'a mut Receiver<T>,
+}
+
+impl<'a, T> Future for Changed<'a, T> {
+    type Output = Result<(), ReceiveError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+
+        if let Poll::Ready(changed) = this.poll_version(cx) {
+            return Poll::Ready(changed);
+        }
+
+        if let Ok(mut wakers) = this.receiver.shared.wakers.lock() {
+            wakers.push(cx.waker().clone());
+        }
+
+        // A value might have landed between the first check and registering the waker.
+        this.poll_version(cx)
+    }
+}
+
+impl<'a, T> Changed<'a, T> {
+    fn poll_version(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), ReceiveError>> {
+        let version = self.receiver.shared.version.load(Ordering::Acquire);
+        if version != self.receiver.seen.get() {
+            self.receiver.seen.set(version);
+            return Poll::Ready(Ok(()));
+        }
+
+        if self.receiver.shared.updater_count.load(Ordering::Acquire) == 0 {
+            return Poll::Ready(Err(ReceiveError::Closed));
+        }
+
+        Poll::Pending
+    }
+}
+
 /// An error that might occur while receiving a value
-#[derive(Debug, Clone)]
-pub struct ReceiveError;
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReceiveError {
+    /// The channel's lock was poisoned, or no updater remains connected.
+    Closed,
+    /// The receiver fell further behind than the buffered channel's capacity; this many
+    /// updates were dropped and the read cursor was fast-forwarded past them.
+    Lagged(u64),
+    /// A `try_*` method would have had to block to acquire the lock.
+    WouldBlock,
+}
@@ -0,0 +1,122 @@
+use std::sync::{RwLockReadGuard, RwLockWriteGuard, TryLockError, TryLockResult};
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+        Condvar, Mutex, RwLock,
+    },
+    task::Waker,
+};
+
+/// State shared between a channel's [`Receiver`](crate::Receiver)s and its
+/// [`Updater`](crate::Updater), kept separate from the value lock so that registering
+/// or waking a [`changed`](crate::Receiver::changed) future never has to wait on it.
+#[derive(Debug)]
+pub(crate) struct Shared<T> {
+    pub(crate) value: RwLock<Option<T>>,
+    pub(crate) version: AtomicU64,
+    pub(crate) wakers: Mutex<Vec<Waker>>,
+    /// Number of live receivers, decremented by [`Receiver`](crate::Receiver)'s `Drop` impl.
+    pub(crate) receiver_count: AtomicUsize,
+    /// Number of live updaters, decremented by [`Updater`](crate::Updater)'s `Drop` impl so the
+    /// last one to go can wake any pending [`changed`](crate::Receiver::changed) future.
+    pub(crate) updater_count: AtomicUsize,
+    pub(crate) closed_wakers: Mutex<Vec<Waker>>,
+    pub(crate) closed_lock: Mutex<()>,
+    pub(crate) closed_condvar: Condvar,
+    /// When set, a poisoned `value` lock is treated as recoverable instead of as an error,
+    /// since a half-written `Option<T>` can never leave this channel's invariants broken.
+    pub(crate) ignore_poison: bool,
+    /// Set whenever [`read_value`](Shared::read_value)/[`write_value`](Shared::write_value) (or
+    /// their `try_*` counterparts) recover from a poisoned lock, so a caller can notice and log
+    /// the recovery instead of it passing silently. Cleared by `clear_poison`.
+    pub(crate) poison_recovered: AtomicBool,
+}
+
+impl<T> Shared<T> {
+    pub(crate) fn new(value: Option<T>, ignore_poison: bool) -> Self {
+        Shared {
+            value: RwLock::new(value),
+            version: AtomicU64::new(0),
+            wakers: Mutex::new(Vec::new()),
+            receiver_count: AtomicUsize::new(1),
+            updater_count: AtomicUsize::new(1),
+            closed_wakers: Mutex::new(Vec::new()),
+            closed_lock: Mutex::new(()),
+            closed_condvar: Condvar::new(),
+            ignore_poison,
+            poison_recovered: AtomicBool::new(false),
+        }
+    }
+
+    /// Acquires the value lock for reading, recovering from poisoning if `ignore_poison` is set.
+    pub(crate) fn read_value(&self) -> Result<RwLockReadGuard<'_, Option<T>>, ()> {
+        match self.value.read() {
+            Ok(guard) => Ok(guard),
+            Err(poisoned) if self.ignore_poison => {
+                self.poison_recovered.store(true, Ordering::Release);
+                Ok(poisoned.into_inner())
+            }
+            Err(_) => Err(()),
+        }
+    }
+
+    /// Acquires the value lock for writing, recovering from poisoning if `ignore_poison` is set.
+    pub(crate) fn write_value(&self) -> Result<RwLockWriteGuard<'_, Option<T>>, ()> {
+        match self.value.write() {
+            Ok(guard) => Ok(guard),
+            Err(poisoned) if self.ignore_poison => {
+                self.poison_recovered.store(true, Ordering::Release);
+                Ok(poisoned.into_inner())
+            }
+            Err(_) => Err(()),
+        }
+    }
+
+    /// Same as [`Shared::read_value`] but never blocks.
+    pub(crate) fn try_read_value(&self) -> TryLockResult<RwLockReadGuard<'_, Option<T>>> {
+        match self.value.try_read() {
+            Ok(guard) => Ok(guard),
+            Err(TryLockError::Poisoned(poisoned)) if self.ignore_poison => {
+                self.poison_recovered.store(true, Ordering::Release);
+                Ok(poisoned.into_inner())
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Same as [`Shared::write_value`] but never blocks.
+    pub(crate) fn try_write_value(&self) -> TryLockResult<RwLockWriteGuard<'_, Option<T>>> {
+        match self.value.try_write() {
+            Ok(guard) => Ok(guard),
+            Err(TryLockError::Poisoned(poisoned)) if self.ignore_poison => {
+                self.poison_recovered.store(true, Ordering::Release);
+                Ok(poisoned.into_inner())
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Wake every future currently waiting for a change and forget about them.
+    pub(crate) fn wake_all(&self) {
+        if let Ok(mut wakers) = self.wakers.lock() {
+            for waker in wakers.drain(..) {
+                waker.wake();
+            }
+        }
+    }
+
+    /// Wake every async and blocking waiter registered on [`Updater::closed`](crate::Updater::closed)
+    /// / [`Updater::wait_closed`](crate::Updater::wait_closed). Called once the last receiver drops.
+    pub(crate) fn wake_closed(&self) {
+        if let Ok(mut wakers) = self.closed_wakers.lock() {
+            for waker in wakers.drain(..) {
+                waker.wake();
+            }
+        }
+
+        if let Ok(guard) = self.closed_lock.lock() {
+            self.closed_condvar.notify_all();
+            drop(guard);
+        }
+    }
+}
@@ -1,12 +1,42 @@
-use std::sync::{RwLock, Weak};
+use crate::shared::Shared;
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{atomic::Ordering, TryLockError, Weak},
+    task::{Context, Poll},
+};
 
 /// The updater half of the update channel.
-/// 
+///
 /// You can update the shared value with [`Updater::update`](struct.Updater.html#method.update). <br />
-/// This doesn't mean that receivers directly hold the new value 
-#[derive(Debug, Clone)]
+/// This doesn't mean that receivers directly hold the new value
+#[derive(Debug)]
 pub struct Updater<T> {
-    pub(crate) lock: Weak<RwLock<Option<T>>>
+    pub(crate) lock: Weak<Shared<T>>
+}
+
+impl<T> Clone for Updater<T> {
+    fn clone(&self) -> Self {
+        if let Some(shared) = self.lock.upgrade() {
+            shared.updater_count.fetch_add(1, Ordering::AcqRel);
+        }
+
+        Self {
+            lock: self.lock.clone(),
+        }
+    }
+}
+
+impl<T> Drop for Updater<T> {
+    fn drop(&mut self) {
+        if let Some(shared) = self.lock.upgrade() {
+            if shared.updater_count.fetch_sub(1, Ordering::AcqRel) == 1 {
+                // Last updater gone: wake every pending `changed` future so it observes
+                // `updater_count == 0` instead of parking forever.
+                shared.wake_all();
+            }
+        }
+    }
 }
 
 impl<T> Updater<T> {
@@ -20,8 +50,10 @@ impl<T> Updater<T> {
     /// no receiver exists an UpdateError holding the value will be returned.
     pub fn update(&self, value: T) -> Result<(), UpdateError<T>> {
         if let Some(shared) = self.lock.upgrade() {
-            if let Ok(mut write) = shared.write() {
+            if let Ok(mut write) = shared.write_value() {
                 *write = Some(value);
+                shared.version.fetch_add(1, Ordering::AcqRel);
+                shared.wake_all();
                 Ok(())
             } else {
                 Err(UpdateError::Poisoned(value))
@@ -30,6 +62,97 @@ impl<T> Updater<T> {
             Err(UpdateError::NoReceiver(value))
         }
     }
+
+    /// Same as [`Updater::update`](struct.Updater.html#method.update) but never blocks:
+    /// returns [`UpdateError::WouldBlock`] holding the value instead of waiting for the lock.
+    pub fn try_update(&self, value: T) -> Result<(), UpdateError<T>> {
+        if let Some(shared) = self.lock.upgrade() {
+            match shared.try_write_value() {
+                Ok(mut write) => {
+                    *write = Some(value);
+                    shared.version.fetch_add(1, Ordering::AcqRel);
+                    shared.wake_all();
+                    Ok(())
+                }
+                Err(TryLockError::WouldBlock) => Err(UpdateError::WouldBlock(value)),
+                Err(TryLockError::Poisoned(_)) => Err(UpdateError::Poisoned(value)),
+            }
+        } else {
+            Err(UpdateError::NoReceiver(value))
+        }
+    }
+
+    /// Waits until every receiver has been dropped, so a producer can stop doing expensive
+    /// work as soon as nobody is listening instead of finding out on the next failed `update`.
+    /// Resolves immediately if no receiver exists already.
+    pub fn closed(&self) -> Closed<'_, T> {
+        Closed { updater: self }
+    }
+
+    /// Blocking variant of [`Updater::closed`] for callers outside of an async runtime.
+    pub fn wait_closed(&self) {
+        if let Some(shared) = self.lock.upgrade() {
+            if let Ok(guard) = shared.closed_lock.lock() {
+                let guard = shared
+                    .closed_condvar
+                    .wait_while(guard, |_| shared.receiver_count.load(Ordering::Acquire) != 0);
+                drop(guard);
+            }
+        }
+    }
+
+    /// Clears the poisoned state of the channel's lock, if a panicking writer poisoned it,
+    /// so that operations on a regular (non-[`channel_ignore_poison`](crate::channel_ignore_poison))
+    /// channel stop returning [`UpdateError::Poisoned`] for it. Also resets
+    /// [`poison_recovered`](Updater::poison_recovered) back to `false`.
+    pub fn clear_poison(&self) {
+        if let Some(shared) = self.lock.upgrade() {
+            shared.value.clear_poison();
+            shared.poison_recovered.store(false, Ordering::Release);
+        }
+    }
+
+    /// Returns whether an operation on this channel has recovered from a poisoned lock by using
+    /// the inner value anyway, rather than surfacing it as an error. Always `false` on a
+    /// regular channel, since there [`UpdateError::Poisoned`] is returned instead of recovering.
+    /// Meaningful on a [`channel_ignore_poison`](crate::channel_ignore_poison) channel, where
+    /// recovery happens automatically and silently unless a caller checks this to log it.
+    /// Cleared by [`clear_poison`](Updater::clear_poison).
+    pub fn poison_recovered(&self) -> bool {
+        self.lock
+            .upgrade()
+            .is_some_and(|shared| shared.poison_recovered.load(Ordering::Acquire))
+    }
+}
+
+/// Future returned by [`Updater::closed`].
+pub struct Closed<'a, T> {
+    updater: &'a Updater<T>,
+}
+
+impl<'a, T> Future for Closed<'a, T> {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let shared = match self.updater.lock.upgrade() {
+            Some(shared) => shared,
+            None => return Poll::Ready(()),
+        };
+
+        if shared.receiver_count.load(Ordering::Acquire) == 0 {
+            return Poll::Ready(());
+        }
+
+        if let Ok(mut wakers) = shared.closed_wakers.lock() {
+            wakers.push(cx.waker().clone());
+        }
+
+        if shared.receiver_count.load(Ordering::Acquire) == 0 {
+            return Poll::Ready(());
+        }
+
+        Poll::Pending
+    }
 }
 
 /// An error occurred while updating the update channel
@@ -37,15 +160,18 @@ pub enum UpdateError<T> {
     /// There is no receiver
     NoReceiver(T),
     /// The RwLock is poisoned
-    Poisoned(T)
+    Poisoned(T),
+    /// `try_update` would have had to block to acquire the lock
+    WouldBlock(T),
 }
 
 impl<T> UpdateError<T> {
-    /// Get contained value the value contained 
+    /// Get contained value the value contained
     pub fn into_inner(self) -> T {
         match self {
             UpdateError::NoReceiver(v) => v,
             UpdateError::Poisoned(v) => v,
+            UpdateError::WouldBlock(v) => v,
         }
     }
 
@@ -54,6 +180,7 @@ impl<T> UpdateError<T> {
         match self {
             UpdateError::NoReceiver(v) => &v,
             UpdateError::Poisoned(v) => &v,
+            UpdateError::WouldBlock(v) => v,
         }
     }
 }